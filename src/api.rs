@@ -1,4 +1,5 @@
 use {
+    crate::{metrics, store},
     anyhow::anyhow,
     ethers::{
         abi::{Function, Param, ParamType, StateMutability, Token},
@@ -13,8 +14,9 @@ use {
     poem::{web::Path, Result},
     poem_openapi::{
         payload::{Json, PlainText},
-        ApiResponse, Object, OpenApi, Tags,
+        ApiResponse, Enum, Object, OpenApi, Tags,
     },
+    rlp_derive::RlpEncodable,
     serde::{Deserialize, Serialize},
     serde_json::Value,
     std::{
@@ -26,7 +28,7 @@ use {
     },
     web3::{
         transports::Http,
-        types::{Bytes, CallRequest, H160, U256},
+        types::{BlockId, BlockNumber, Bytes, CallRequest, H160, H256, U256, U64},
         Web3,
     },
     zei::{setup::PublicParams, xfr::asset_record::AssetRecordType},
@@ -35,6 +37,10 @@ pub struct Api {
     pub findora_query_url: String,
     pub support_chain: HashMap<U256, (Web3<Http>, Vec<H160>)>,
     pub dir_path: PathBuf,
+    /// Number of blocks a balance read must be buried under before it is honored.
+    pub confirmations: u64,
+    /// Persistent index of already-issued assets, guarding against double-minting.
+    pub issuance_store: store::IssuanceStore,
 }
 
 #[derive(Tags)]
@@ -61,6 +67,16 @@ pub enum PingRespEnum {
     Ok(PlainText<String>),
 }
 
+/// Which ERC token standard `token_address` implements.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TokenStandard {
+    Erc20,
+    Erc721,
+    Erc1155,
+}
+
 #[derive(Serialize, Deserialize, Debug, Object, Clone)]
 pub struct GetIssueTxReq {
     pub id: String,
@@ -69,11 +85,38 @@ pub struct GetIssueTxReq {
     pub chainid: String,
     pub token_address: String,
     pub tokenid: String,
-    pub is_721: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_standard: Option<TokenStandard>,
+    /// Deprecated: use `token_standard`. `true` maps to `Erc721`, `false` to `Erc1155`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_721: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rand_str: Option<String>,
 }
 
+impl GetIssueTxReq {
+    /// Resolves the effective token standard, falling back to the deprecated
+    /// `is_721` flag when `token_standard` is not set.
+    pub fn token_standard(&self) -> TokenStandard {
+        self.token_standard.unwrap_or(match self.is_721 {
+            Some(true) => TokenStandard::Erc721,
+            _ => TokenStandard::Erc1155,
+        })
+    }
+}
+
+impl TokenStandard {
+    /// Stable, compact encoding used wherever the standard is bound into a
+    /// hash (the EIP-712 signature and the issuance index keys).
+    fn code(self) -> u8 {
+        match self {
+            TokenStandard::Erc20 => 0,
+            TokenStandard::Erc721 => 1,
+            TokenStandard::Erc1155 => 2,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Object, Clone)]
 pub struct GetIssueTxResp {
     pub id: String,
@@ -99,6 +142,18 @@ pub enum GetIssueInfo {
     Ok(Json<String>),
 }
 
+#[derive(ApiResponse)]
+pub enum MetricsRespEnum {
+    #[oai(status = 200)]
+    Ok(PlainText<String>),
+}
+
+#[derive(ApiResponse)]
+pub enum GetIssueStatus {
+    #[oai(status = 200)]
+    Ok(Json<Option<store::IssuanceRecord>>),
+}
+
 #[OpenApi]
 impl Api {
     #[oai(path = "/version", method = "get", tag = "ApiTags::Version")]
@@ -147,23 +202,56 @@ impl Api {
         )))
     }
 
+    #[oai(path = "/metrics", method = "get", tag = "ApiTags::Version")]
+    async fn metrics(&self) -> Result<MetricsRespEnum> {
+        Ok(MetricsRespEnum::Ok(PlainText(metrics::gather())))
+    }
+
+    #[oai(
+        path = "/get_issue_status/:chainid/:token_address/:tokenid/:token_standard",
+        method = "get",
+        tag = "ApiTags::Transaction"
+    )]
+    async fn get_issue_status(
+        &self,
+        chainid: Path<String>,
+        token_address: Path<String>,
+        tokenid: Path<String>,
+        token_standard: Path<TokenStandard>,
+    ) -> Result<GetIssueStatus> {
+        let record = U256::from_str(&chainid)
+            .ok()
+            .zip(H160::from_str(&token_address).ok())
+            .zip(U256::from_str(&tokenid).ok())
+            .and_then(|((chainid, token_address), tokenid)| {
+                let tuple_key =
+                    tuple_preimage(token_address, tokenid, chainid, token_standard.0.code());
+                self.issuance_store
+                    .get_by_tuple_key(&tuple_key)
+                    .ok()
+                    .flatten()
+            });
+        Ok(GetIssueStatus::Ok(Json(record)))
+    }
+
     #[oai(
         path = "/get_issue_transaction",
         method = "post",
         tag = "ApiTags::Transaction"
     )]
     async fn get_issue_transaction(&self, req: Json<GetIssueTxReq>) -> Result<GetIssueTxRespEnum> {
+        metrics::REQUESTS_TOTAL.with_label_values(&[]).inc();
         let mut resp = GetIssueTxResp {
             id: req.0.id.clone(),
             code: 0,
             msg: String::new(),
         };
-        let address = match get_address_and_pub_key(&req.0.receive_public_key, &req.0.signature) {
+        let receiver = match H160::from_str(&req.0.receive_public_key) {
             Ok(v) => v,
-            Err((code, msg)) => {
-                resp.code = code;
-                resp.msg = msg;
-                return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+            Err(e) => {
+                resp.code = -1;
+                resp.msg = format!("receive_public_key format error:{:?}", e);
+                return respond(resp);
             }
         };
         let chainid = match U256::from_str(&req.chainid) {
@@ -171,7 +259,7 @@ impl Api {
             Err(e) => {
                 resp.code = -30;
                 resp.msg = format!("chainid format error:{:?}", e);
-                return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+                return respond(resp);
             }
         };
         let token_address = match H160::from_str(&req.token_address) {
@@ -179,52 +267,106 @@ impl Api {
             Err(e) => {
                 resp.code = -31;
                 resp.msg = format!("token_address format error:{:?}", e);
-                return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+                return respond(resp);
+            }
+        };
+        let tokenid = match U256::from_str(&req.tokenid) {
+            Ok(v) => v,
+            Err(e) => {
+                resp.code = -35;
+                resp.msg = format!("tokenid format error:{:?}", e);
+                return respond(resp);
             }
         };
+        let token_standard = req.0.token_standard();
+        let address = match get_address_and_pub_key(
+            receiver,
+            chainid,
+            token_address,
+            tokenid,
+            token_standard.code(),
+            req.0.rand_str.as_deref().unwrap_or_default(),
+            &req.0.signature,
+        ) {
+            Ok(v) => v,
+            Err((code, msg)) => {
+                resp.code = code;
+                resp.msg = msg;
+                return respond(resp);
+            }
+        };
+        if address != receiver {
+            resp.code = -6;
+            resp.msg = String::from("signature does not match receive_public_key");
+            return respond(resp);
+        }
 
         let (web3, contract_address) = match self.support_chain.get(&chainid) {
             Some(v) => v,
             None => {
                 resp.code = -32;
                 resp.msg = String::from("chain not support");
-                return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+                return respond(resp);
             }
         };
         if !contract_address.contains(&token_address) {
             resp.code = -33;
             resp.msg = String::from("token_address not support");
-            return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+            return respond(resp);
         }
-        let tokenid = match U256::from_str(&req.tokenid) {
-            Ok(v) => v,
-            Err(e) => {
-                resp.code = -35;
-                resp.msg = format!("tokenid format error:{:?}", e);
-                return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+        let (mut balance, decimals) = match token_standard {
+            TokenStandard::Erc20 => {
+                let _timer = metrics::BALANCE_LOOKUP_DURATION
+                    .with_label_values(&["erc20"])
+                    .start_timer();
+                match get_erc20_balance(&web3, token_address, address, self.confirmations).await {
+                    Ok(v) => v,
+                    Err((code, msg)) => {
+                        resp.code = code;
+                        resp.msg = msg;
+                        return respond(resp);
+                    }
+                }
             }
-        };
-        let mut balance = if req.is_721 {
-            match get_erc_balance(&web3, token_address, address).await {
-                Ok(v) => v,
-                Err((code, msg)) => {
-                    resp.code = code;
-                    resp.msg = msg;
-                    return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+            TokenStandard::Erc721 => {
+                let _timer = metrics::BALANCE_LOOKUP_DURATION
+                    .with_label_values(&["erc721"])
+                    .start_timer();
+                match get_erc_balance(&web3, token_address, address, self.confirmations).await {
+                    Ok(v) => (v, 6),
+                    Err((code, msg)) => {
+                        resp.code = code;
+                        resp.msg = msg;
+                        return respond(resp);
+                    }
                 }
             }
-        } else {
-            match get_1155_balance(&web3, token_address, address, tokenid).await {
-                Ok(v) => v,
-                Err((code, msg)) => {
-                    resp.code = code;
-                    resp.msg = msg;
-                    return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+            TokenStandard::Erc1155 => {
+                let _timer = metrics::BALANCE_LOOKUP_DURATION
+                    .with_label_values(&["erc1155"])
+                    .start_timer();
+                match get_1155_balance(&web3, token_address, address, tokenid, self.confirmations)
+                    .await
+                {
+                    Ok(v) => (v, 6),
+                    Err((code, msg)) => {
+                        resp.code = code;
+                        resp.msg = msg;
+                        return respond(resp);
+                    }
                 }
             }
         };
 
         if balance > U256::from(u64::MAX) {
+            if token_standard == TokenStandard::Erc20 {
+                resp.code = -37;
+                resp.msg = format!(
+                    "erc20 balance {} exceeds u64::MAX and cannot be minted without truncation",
+                    balance
+                );
+                return respond(resp);
+            }
             balance = U256::from(u64::MAX);
         }
         if balance.is_zero() {
@@ -233,90 +375,180 @@ impl Api {
                 "balance is zero account: {:?} chainid:{} contract_address:{:?}",
                 address, chainid, token_address
             );
-            return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+            return respond(resp);
         }
-        let mut data = vec![];
-        {
-            data.extend(token_address.0);
-            let chain_id = match web3.eth().chain_id().await {
-                Ok(v) => v,
-                Err(e) => {
-                    resp.code = -40;
-                    resp.msg = format!("error: {:?}", e);
-                    return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
-                }
-            };
-            let mut tmp: [u8; 32] = [0; 32];
-            tokenid.to_big_endian(&mut tmp);
-            data.extend(&tmp);
-            tmp = [0; 32];
-            chain_id.to_big_endian(&mut tmp);
-            data.extend(&tmp);
-            if let Some(v) = &req.rand_str {
-                data.extend(v.as_bytes());
+        let chain_id = match web3.eth().chain_id().await {
+            Ok(v) => {
+                metrics::CHAIN_RPC_UP
+                    .with_label_values(&[&chainid.to_string()])
+                    .set(1);
+                v
+            }
+            Err(e) => {
+                metrics::CHAIN_RPC_UP
+                    .with_label_values(&[&chainid.to_string()])
+                    .set(0);
+                resp.code = -40;
+                resp.msg = format!("error: {:?}", e);
+                return respond(resp);
+            }
+        };
+        let preimage = AssetCodePreimage {
+            token_address,
+            tokenid,
+            chain_id,
+            token_standard: token_standard.code(),
+            rand_str: req.rand_str.clone().unwrap_or_default().into_bytes(),
+        };
+        let preimage_key = keccak256(rlp::encode(&preimage));
+        let tuple_key = tuple_preimage(token_address, tokenid, chainid, token_standard.code());
+
+        match self.issuance_store.reserve(&preimage_key) {
+            Ok(store::Reservation::Reserved) => {}
+            Ok(store::Reservation::AlreadyIssued(record)) => {
+                resp.code = -90;
+                resp.msg = match record {
+                    Some(record) => format!("already issued as asset_code: {}", record.asset_code),
+                    None => String::from("issuance already in progress"),
+                };
+                return respond(resp);
+            }
+            Err(e) => {
+                resp.code = -91;
+                resp.msg = format!("issuance index error: {:?}", e);
+                return respond(resp);
             }
         }
 
-        let (builder, asset_code) =
-            match create_asset_tx(&self.findora_query_url, &keccak256(data), balance.as_u64()) {
+        let (builder, asset_code) = {
+            let _timer = metrics::CREATE_ASSET_TX_DURATION.start_timer();
+            match create_asset_tx(
+                &self.findora_query_url,
+                &preimage_key,
+                balance.as_u64(),
+                decimals,
+            ) {
                 Ok(v) => v,
                 Err((code, msg)) => {
+                    let _ = self.issuance_store.release(&preimage_key);
                     resp.code = code;
                     resp.msg = msg;
-                    return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+                    return respond(resp);
                 }
-            };
+            }
+        };
         resp.msg = match serde_json::to_string(&builder) {
             Ok(v) => v,
             Err(e) => {
+                let _ = self.issuance_store.release(&preimage_key);
                 resp.code = -50;
                 resp.msg = format!("error: {:?}", e);
-                return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+                return respond(resp);
             }
         };
         let hex_code = hex::encode(&asset_code);
-        let file_name = self.dir_path.join(hex_code);
+        let file_name = self.dir_path.join(&hex_code);
         let mut file = match File::create(file_name) {
             Ok(v) => v,
             Err(e) => {
+                let _ = self.issuance_store.release(&preimage_key);
                 resp.code = -60;
                 resp.msg = format!("save file error: {:?}", e);
-                return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+                return respond(resp);
             }
         };
         let json = match serde_json::to_string(&req.0) {
             Ok(v) => v,
             Err(e) => {
+                let _ = self.issuance_store.release(&preimage_key);
                 resp.code = -70;
                 resp.msg = format!("save file error: {:?}", e);
-                return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+                return respond(resp);
             }
         };
         if let Err(e) = file.write_all(json.as_bytes()) {
+            let _ = self.issuance_store.release(&preimage_key);
             resp.code = -80;
             resp.msg = format!("save file error: {:?}", e);
-            return Ok(GetIssueTxRespEnum::Ok(Json(resp)));
+            return respond(resp);
+        };
+
+        let record = store::IssuanceRecord {
+            asset_code: hex_code,
+            amount: balance.as_u64(),
+            receiver: format!("{:?}", address),
+            timestamp: store::now_unix(),
         };
+        if let Err(e) = self
+            .issuance_store
+            .commit(&preimage_key, &tuple_key, &record)
+        {
+            let _ = self.issuance_store.release(&preimage_key);
+            resp.code = -92;
+            resp.msg = format!("issuance index error: {:?}", e);
+            return respond(resp);
+        }
 
-        Ok(GetIssueTxRespEnum::Ok(Json(resp)))
+        respond(resp)
     }
 }
 
+/// Canonical, RLP-encoded preimage of the derived asset code. Using RLP list
+/// encoding (rather than raw concatenation) frames `rand_str` by its own
+/// length, instead of relying on it being the last field, so the mapping
+/// from request parameters to asset code stays injective as the schema
+/// grows new fields.
+#[derive(RlpEncodable)]
+struct AssetCodePreimage {
+    token_address: H160,
+    tokenid: U256,
+    chain_id: U256,
+    token_standard: u8,
+    rand_str: Vec<u8>,
+}
+
+/// Key used to look up issuance status by `(chainid, token_address, tokenid, token_standard)`
+/// alone, independent of the `rand_str` folded into the full asset-code preimage. Folding in
+/// `token_standard` keeps an ERC-20, ERC-721 and ERC-1155 request for the same tokenid from
+/// colliding on the same issuance-index entry.
+fn tuple_preimage(token_address: H160, tokenid: U256, chainid: U256, token_standard: u8) -> [u8; 32] {
+    let mut data = Vec::with_capacity(20 + 32 + 32 + 1);
+    data.extend(token_address.0);
+    let mut tmp: [u8; 32] = [0; 32];
+    tokenid.to_big_endian(&mut tmp);
+    data.extend(&tmp);
+    tmp = [0; 32];
+    chainid.to_big_endian(&mut tmp);
+    data.extend(&tmp);
+    data.push(token_standard);
+    keccak256(data)
+}
+
+/// Records the final result `code` before wrapping the response, so every
+/// exit path from `get_issue_transaction` is reflected in
+/// [`metrics::REQUESTS_BY_CODE`].
+fn respond(resp: GetIssueTxResp) -> Result<GetIssueTxRespEnum> {
+    metrics::REQUESTS_BY_CODE
+        .with_label_values(&[&resp.code.to_string()])
+        .inc();
+    Ok(GetIssueTxRespEnum::Ok(Json(resp)))
+}
+
 fn create_asset_tx(
     url: &str,
     code: &[u8],
     amount: u64,
+    decimals: u8,
 ) -> Result<(TransactionBuilder, Vec<u8>), (i32, String)> {
     let code = AssetTypeCode::from_bytes(code).map_err(|e| (-21, format!("error: {:?}", e)))?;
 
     let asset_code = get_derived_asset_code(url, &code).map_err(|e| (-21, format!("error: {:?}", e)))?;
 
     let mut rules = AssetRules::default();
-    let decimal = 6;
     let max_units = None;
     let transferable = true;
     rules
-        .set_decimals(decimal)
+        .set_decimals(decimals)
         .map_err(|e| (-22, format!("error: {:?}", e)))?;
     rules.set_max_units(max_units);
     rules.set_transferable(transferable);
@@ -371,10 +603,118 @@ fn get_derived_asset_code(url: &str, raw_asset_code: &AssetTypeCode) -> anyhow::
     })
 }
 
+/// Pins a balance read to a block buried under `confirmations` blocks of the chain tip.
+async fn pinned_block(web3: &Web3<Http>, confirmations: u64) -> Result<BlockId, (i32, String)> {
+    let tip = web3
+        .eth()
+        .block_number()
+        .await
+        .map_err(|e| (-16, format!("error: {:?}", e)))?;
+    let confirmations = U64::from(confirmations);
+    let pinned = if tip > confirmations {
+        tip - confirmations
+    } else {
+        U64::zero()
+    };
+    Ok(BlockId::Number(BlockNumber::Number(pinned)))
+}
+
+/// Reads an ERC-20 token's `balanceOf(address)` together with its `decimals()`,
+/// so the caller can mint a Findora asset at the same precision as the source
+/// token instead of an arbitrary hardcoded one.
+async fn get_erc20_balance(
+    web3: &Web3<Http>,
+    contract_address: H160,
+    address: H160,
+    confirmations: u64,
+) -> anyhow::Result<(U256, u8), (i32, String)> {
+    #[allow(deprecated)]
+    let balance_of = Function {
+        name: String::from("balanceOf"),
+        inputs: vec![Param {
+            name: String::from("account"),
+            kind: ParamType::Address,
+            internal_type: Some(String::from("address")),
+        }],
+        outputs: vec![Param {
+            name: String::new(),
+            kind: ParamType::Uint(256),
+            internal_type: Some(String::from("uint256")),
+        }],
+        constant: None,
+        state_mutability: StateMutability::Payable,
+    };
+    #[allow(deprecated)]
+    let decimals = Function {
+        name: String::from("decimals"),
+        inputs: vec![],
+        outputs: vec![Param {
+            name: String::new(),
+            kind: ParamType::Uint(8),
+            internal_type: Some(String::from("uint8")),
+        }],
+        constant: None,
+        state_mutability: StateMutability::View,
+    };
+
+    let block = pinned_block(web3, confirmations).await?;
+
+    let balance_data = balance_of
+        .encode_input(&vec![Token::Address(address)])
+        .map_err(|e| (-41, format!("error: {:?}", e)))?;
+    let balance_bytes = web3
+        .eth()
+        .call(
+            CallRequest {
+                to: Some(contract_address),
+                data: Some(Bytes(balance_data)),
+                ..Default::default()
+            },
+            Some(block),
+        )
+        .await
+        .map_err(|e| (-42, format!("error: {:?}", e)))?;
+    let balance_vts = balance_of
+        .decode_output(&balance_bytes.0)
+        .map_err(|e| (-43, format!("error: {:?}", e)))?;
+    let balance = match balance_vts.get(0).cloned() {
+        Some(Token::Uint(v)) => v,
+        Some(_) => return Err((-44, String::from("balance return type error"))),
+        None => return Err((-45, String::from("balance not found"))),
+    };
+
+    let decimals_data = decimals
+        .encode_input(&[])
+        .map_err(|e| (-17, format!("error: {:?}", e)))?;
+    let decimals_bytes = web3
+        .eth()
+        .call(
+            CallRequest {
+                to: Some(contract_address),
+                data: Some(Bytes(decimals_data)),
+                ..Default::default()
+            },
+            Some(block),
+        )
+        .await
+        .map_err(|e| (-18, format!("error: {:?}", e)))?;
+    let decimals_vts = decimals
+        .decode_output(&decimals_bytes.0)
+        .map_err(|e| (-19, format!("error: {:?}", e)))?;
+    let decimals = match decimals_vts.get(0).cloned() {
+        Some(Token::Uint(v)) => v.as_u32() as u8,
+        Some(_) => return Err((-20, String::from("decimals return type error"))),
+        None => return Err((-34, String::from("decimals not found"))),
+    };
+
+    Ok((balance, decimals))
+}
+
 async fn get_erc_balance(
     web3: &Web3<Http>,
     contract_address: H160,
     address: H160,
+    confirmations: u64,
 ) -> anyhow::Result<U256, (i32, String)> {
     #[allow(deprecated)]
     let function = Function {
@@ -396,6 +736,7 @@ async fn get_erc_balance(
         .encode_input(&vec![Token::Address(address)])
         .map_err(|e| (-11, format!("error: {:?}", e)))?;
 
+    let block = pinned_block(web3, confirmations).await?;
     let bytes = web3
         .eth()
         .call(
@@ -404,7 +745,7 @@ async fn get_erc_balance(
                 data: Some(Bytes(data)),
                 ..Default::default()
             },
-            None,
+            Some(block),
         )
         .await
         .map_err(|e| (-12, format!("error: {:?}", e)))?;
@@ -430,6 +771,7 @@ async fn get_1155_balance(
     contract_address: H160,
     address: H160,
     tokenid: U256,
+    confirmations: u64,
 ) -> anyhow::Result<U256, (i32, String)> {
     #[allow(deprecated)]
     let function = Function {
@@ -458,6 +800,7 @@ async fn get_1155_balance(
         .encode_input(&vec![Token::Address(address), Token::Uint(tokenid)])
         .map_err(|e| (-11, format!("error: {:?}", e)))?;
 
+    let block = pinned_block(web3, confirmations).await?;
     let bytes = web3
         .eth()
         .call(
@@ -466,7 +809,7 @@ async fn get_1155_balance(
                 data: Some(Bytes(data)),
                 ..Default::default()
             },
-            None,
+            Some(block),
         )
         .await
         .map_err(|e| (-12, format!("error: {:?}", e)))?;
@@ -487,7 +830,76 @@ async fn get_1155_balance(
     }
 }
 
-fn get_address_and_pub_key(message: &str, signature: &str) -> Result<H160, (i32, String)> {
+/// EIP-712 `EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)`.
+const EIP712_DOMAIN_TYPE_PREIMAGE: &[u8] =
+    b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+/// EIP-712 `Issue(address receiver,uint256 chainId,address token,uint256 tokenId,uint8 tokenStandard,string rand)`.
+const ISSUE_TYPE_PREIMAGE: &[u8] = b"Issue(address receiver,uint256 chainId,address token,uint256 tokenId,uint8 tokenStandard,string rand)";
+
+fn abi_encode_address(addr: H160) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[12..].copy_from_slice(&addr.0);
+    out
+}
+
+fn abi_encode_u256(v: U256) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    v.to_big_endian(&mut out);
+    out
+}
+
+fn abi_encode_u8(v: u8) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[31] = v;
+    out
+}
+
+/// `keccak256(abi.encode(EIP712Domain type hash, name, version, chainId, verifyingContract))`,
+/// binding a signature to this service ("nft-issue", version "1") and to the specific
+/// chain/token it was requested for.
+fn eip712_domain_separator(chain_id: U256, verifying_contract: H160) -> [u8; 32] {
+    let mut data = Vec::with_capacity(32 * 5);
+    data.extend_from_slice(&keccak256(EIP712_DOMAIN_TYPE_PREIMAGE));
+    data.extend_from_slice(&keccak256(b"nft-issue"));
+    data.extend_from_slice(&keccak256(b"1"));
+    data.extend_from_slice(&abi_encode_u256(chain_id));
+    data.extend_from_slice(&abi_encode_address(verifying_contract));
+    keccak256(data)
+}
+
+/// `keccak256(abi.encode(Issue type hash, receiver, chainId, token, tokenId, tokenStandard, keccak256(rand)))`.
+fn eip712_issue_struct_hash(
+    receiver: H160,
+    chain_id: U256,
+    token: H160,
+    token_id: U256,
+    token_standard: u8,
+    rand: &str,
+) -> [u8; 32] {
+    let mut data = Vec::with_capacity(32 * 7);
+    data.extend_from_slice(&keccak256(ISSUE_TYPE_PREIMAGE));
+    data.extend_from_slice(&abi_encode_address(receiver));
+    data.extend_from_slice(&abi_encode_u256(chain_id));
+    data.extend_from_slice(&abi_encode_address(token));
+    data.extend_from_slice(&abi_encode_u256(token_id));
+    data.extend_from_slice(&abi_encode_u8(token_standard));
+    data.extend_from_slice(&keccak256(rand.as_bytes()));
+    keccak256(data)
+}
+
+/// Recovers the signer of an EIP-712 typed-data signature over the request's own
+/// `receiver`/`chainId`/`token`/`tokenId`/`tokenStandard`/`rand` fields, so a signature
+/// can't be replayed against a different chain, token, receiver or token standard than
+/// the one it was issued for.
+fn get_address_and_pub_key(
+    receiver: H160,
+    chain_id: U256,
+    token: H160,
+    token_id: U256,
+    token_standard: u8,
+    rand: &str,
+    signature: &str,
+) -> Result<H160, (i32, String)> {
     let s = signature.strip_prefix("0x").unwrap_or(signature);
     let signature = hex::decode(s)
         .map_err(|e| (-3, format!("error: {:?}", e)))
@@ -495,9 +907,61 @@ fn get_address_and_pub_key(message: &str, signature: &str) -> Result<H160, (i32,
             Signature::try_from(v.as_slice()).map_err(|e| (-4, format!("error: {:?}", e)))
         })?;
 
+    let domain_separator = eip712_domain_separator(chain_id, token);
+    let struct_hash =
+        eip712_issue_struct_hash(receiver, chain_id, token, token_id, token_standard, rand);
+    let mut preimage = Vec::with_capacity(2 + 32 + 32);
+    preimage.push(0x19);
+    preimage.push(0x01);
+    preimage.extend_from_slice(&domain_separator);
+    preimage.extend_from_slice(&struct_hash);
+    let digest = keccak256(preimage);
+
     let address = signature
-        .recover(message)
+        .recover(H256::from(digest))
         .map_err(|e| (-5, format!("error: {:?}", e)))?;
 
     Ok(address)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECEIVER: H160 = H160([0x11; 20]);
+    const TOKEN: H160 = H160([0x22; 20]);
+
+    #[test]
+    fn eip712_domain_separator_known_answer() {
+        let got = eip712_domain_separator(U256::from(1u64), TOKEN);
+        let want =
+            hex::decode("f0dba784ad456db294c2b7655c7f716894c0a3beb6d7dbe84a176cd1b2368a85")
+                .unwrap();
+        assert_eq!(got.as_slice(), want.as_slice());
+    }
+
+    #[test]
+    fn eip712_issue_struct_hash_known_answer() {
+        let got = eip712_issue_struct_hash(RECEIVER, U256::from(1u64), TOKEN, U256::from(7u64), 1, "abc");
+        let want =
+            hex::decode("c51fb57dd7540a48029a7b0f7f63c0179552555033e8ed7d8da8e5030961e31b")
+                .unwrap();
+        assert_eq!(got.as_slice(), want.as_slice());
+    }
+
+    #[test]
+    fn asset_code_preimage_known_answer() {
+        let preimage = AssetCodePreimage {
+            token_address: TOKEN,
+            tokenid: U256::from(7u64),
+            chain_id: U256::from(1u64),
+            token_standard: 1,
+            rand_str: b"abc".to_vec(),
+        };
+        let key = keccak256(rlp::encode(&preimage));
+        let want =
+            hex::decode("91f4c37b0a476612a615eb1f12c3dc2ddb9b1f1ee25096dabc2d9f33d39ed6ba")
+                .unwrap();
+        assert_eq!(key.as_slice(), want.as_slice());
+    }
+}
@@ -12,6 +12,10 @@ pub struct Config {
     pub listen_port: u32,
     pub findora_query_url: String,
     pub support_chain: HashMap<String, Vec<H160>>,
+    /// Number of blocks a balance read must be buried under before it is honored.
+    /// Defaults to 0 (read the chain tip) for configs predating this field.
+    #[serde(default)]
+    pub confirmations: u64,
 }
 impl Config {
     pub fn new(path: &str) -> Result<Self> {
@@ -1,5 +1,7 @@
 mod api;
 mod config;
+mod metrics;
+mod store;
 
 use {
     anyhow::Result,
@@ -9,6 +11,7 @@ use {
     poem::{listener::TcpListener, middleware::Cors, EndpointExt, Route, Server},
     poem_openapi::OpenApiService,
     std::{collections::HashMap, fs::create_dir_all, path::PathBuf},
+    store::IssuanceStore,
     web3::types::H160,
     web3::{transports::Http, Web3},
 };
@@ -27,10 +30,14 @@ async fn main() -> Result<()> {
     if !dir_path.exists() {
         create_dir_all(&dir_path)?;
     }
+    let issuance_store = IssuanceStore::open(&dir_path.join("issuance_index"))?;
+    tokio::spawn(probe_chain_rpcs(support_chain.clone()));
     let api = Api {
         support_chain,
         findora_query_url: config.findora_query_url,
         dir_path,
+        confirmations: config.confirmations,
+        issuance_store,
     };
     let api_service = OpenApiService::new(api, "zk-nft", "1.0").server(config.swagger_url);
     let ui = api_service.swagger_ui();
@@ -48,3 +55,19 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Periodically probes every configured chain's RPC with `eth_chainId` and
+/// updates `CHAIN_RPC_UP`, independent of whether any client traffic is
+/// hitting that chain.
+async fn probe_chain_rpcs(support_chain: HashMap<U256, (Web3<Http>, Vec<H160>)>) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+    loop {
+        interval.tick().await;
+        for (chainid, (web3, _)) in &support_chain {
+            let up = web3.eth().chain_id().await.is_ok();
+            metrics::CHAIN_RPC_UP
+                .with_label_values(&[&chainid.to_string()])
+                .set(up as i64);
+        }
+    }
+}
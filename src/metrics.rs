@@ -0,0 +1,102 @@
+//! Prometheus metrics for the NFT issuance API, following the same
+//! registry-of-lazily-registered-collectors pattern electrs uses for its
+//! own `/metrics` endpoint.
+use {
+    once_cell::sync::Lazy,
+    prometheus::{
+        Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts,
+        Registry, TextEncoder,
+    },
+};
+
+pub static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+/// Total number of `get_issue_transaction` requests received.
+pub static REQUESTS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new(
+            "nft_issue_requests_total",
+            "Total number of get_issue_transaction requests",
+        ),
+        &[],
+    )
+    .expect("metric can be created");
+    REGISTRY
+        .register(Box::new(counter.clone()))
+        .expect("metric can be registered");
+    counter
+});
+
+/// `get_issue_transaction` responses bucketed by the `code` field returned
+/// to the caller (0 for success, negative error codes otherwise).
+pub static REQUESTS_BY_CODE: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new(
+            "nft_issue_requests_by_code_total",
+            "Number of get_issue_transaction responses by result code",
+        ),
+        &["code"],
+    )
+    .expect("metric can be created");
+    REGISTRY
+        .register(Box::new(counter.clone()))
+        .expect("metric can be registered");
+    counter
+});
+
+/// Latency of the on-chain `balanceOf` lookup, labeled by token standard.
+pub static BALANCE_LOOKUP_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    let histogram = HistogramVec::new(
+        HistogramOpts::new(
+            "nft_issue_balance_lookup_duration_seconds",
+            "Latency of ERC20/ERC721/ERC1155 balance lookups",
+        ),
+        &["standard"],
+    )
+    .expect("metric can be created");
+    REGISTRY
+        .register(Box::new(histogram.clone()))
+        .expect("metric can be registered");
+    histogram
+});
+
+/// Latency of building a Findora `create_asset_tx`.
+pub static CREATE_ASSET_TX_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    let histogram = Histogram::with_opts(HistogramOpts::new(
+        "nft_issue_create_asset_tx_duration_seconds",
+        "Latency of Findora create_asset_tx calls",
+    ))
+    .expect("metric can be created");
+    REGISTRY
+        .register(Box::new(histogram.clone()))
+        .expect("metric can be registered");
+    histogram
+});
+
+/// Whether the upstream RPC for a supported chain answered the last
+/// `eth_chainId` probe (1) or not (0), labeled by chain id.
+pub static CHAIN_RPC_UP: Lazy<IntGaugeVec> = Lazy::new(|| {
+    let gauge = IntGaugeVec::new(
+        Opts::new(
+            "nft_issue_chain_rpc_up",
+            "Whether the upstream RPC for a chain is currently reachable",
+        ),
+        &["chainid"],
+    )
+    .expect("metric can be created");
+    REGISTRY
+        .register(Box::new(gauge.clone()))
+        .expect("metric can be registered");
+    gauge
+});
+
+/// Render all registered collectors in Prometheus text exposition format.
+pub fn gather() -> String {
+    let encoder = TextEncoder::new();
+    let metric_families = REGISTRY.gather();
+    let mut buffer = Vec::new();
+    encoder
+        .encode(&metric_families, &mut buffer)
+        .expect("metrics can be encoded");
+    String::from_utf8(buffer).expect("metrics are valid utf8")
+}
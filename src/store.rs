@@ -0,0 +1,112 @@
+//! Persistent issuance index, keyed on the keccak256 preimage used to derive
+//! an asset's Findora asset code (similar to how electrs keeps an embedded,
+//! keyed index of the chain it serves), so the same
+//! `(chainid, token_address, tokenid, token_standard, rand_str)` can never mint a duplicate
+//! Findora asset.
+use {
+    anyhow::Result,
+    poem_openapi::Object,
+    serde::{Deserialize, Serialize},
+    std::{path::Path, time::SystemTime},
+};
+
+#[derive(Debug, Serialize, Deserialize, Object, Clone)]
+pub struct IssuanceRecord {
+    pub asset_code: String,
+    pub amount: u64,
+    pub receiver: String,
+    pub timestamp: u64,
+}
+
+/// Marks a preimage key as "being issued" until `commit` replaces it with the
+/// final [`IssuanceRecord`], so a concurrent request for the same key is
+/// rejected instead of racing past the check.
+const PENDING_MARKER: &[u8] = b"__pending__";
+
+/// Outcome of [`IssuanceStore::reserve`].
+pub enum Reservation {
+    /// No prior or in-flight issuance for this key; caller may proceed and
+    /// must eventually call `commit` or `release`.
+    Reserved,
+    /// Another request already finished (carries the record) or is
+    /// currently in flight (`None`) for this key.
+    AlreadyIssued(Option<IssuanceRecord>),
+}
+
+/// Embedded, keyed store of already-issued assets. One tree is keyed on the
+/// full asset-code preimage (the source of truth used to reject replays),
+/// the other on the `(chainid, token_address, tokenid, token_standard)` tuple alone, so
+/// clients can look up issuance status without knowing the `rand_str` that
+/// was used.
+pub struct IssuanceStore {
+    by_preimage: sled::Tree,
+    by_tuple: sled::Tree,
+}
+
+impl IssuanceStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let db = sled::open(path)?;
+        let by_preimage = db.open_tree("issuance_by_preimage")?;
+        let by_tuple = db.open_tree("issuance_by_tuple")?;
+        Ok(Self {
+            by_preimage,
+            by_tuple,
+        })
+    }
+
+    pub fn get_by_tuple_key(&self, key: &[u8]) -> Result<Option<IssuanceRecord>> {
+        get(&self.by_tuple, key)
+    }
+
+    /// Atomically reserves `preimage_key` so a concurrent caller for the same
+    /// key observes `AlreadyIssued` instead of both sides racing past a
+    /// plain check-then-insert.
+    pub fn reserve(&self, preimage_key: &[u8]) -> Result<Reservation> {
+        match self
+            .by_preimage
+            .compare_and_swap(preimage_key, None::<&[u8]>, Some(PENDING_MARKER))?
+        {
+            Ok(()) => Ok(Reservation::Reserved),
+            Err(existing) => {
+                let record = existing
+                    .current
+                    .filter(|v| v.as_ref() != PENDING_MARKER)
+                    .and_then(|v| serde_json::from_slice(&v).ok());
+                Ok(Reservation::AlreadyIssued(record))
+            }
+        }
+    }
+
+    /// Releases a reservation taken by `reserve` when issuance failed after
+    /// the reservation was made, so the key can be retried.
+    pub fn release(&self, preimage_key: &[u8]) -> Result<()> {
+        self.by_preimage
+            .compare_and_swap(preimage_key, Some(PENDING_MARKER), None::<&[u8]>)??;
+        self.by_preimage.flush()?;
+        Ok(())
+    }
+
+    /// Replaces a reservation with the final record once issuance succeeded.
+    pub fn commit(&self, preimage_key: &[u8], tuple_key: &[u8], record: &IssuanceRecord) -> Result<()> {
+        let bytes = serde_json::to_vec(record)?;
+        self.by_preimage.insert(preimage_key, bytes.clone())?;
+        self.by_tuple.insert(tuple_key, bytes)?;
+        self.by_preimage.flush()?;
+        self.by_tuple.flush()?;
+        Ok(())
+    }
+}
+
+fn get(tree: &sled::Tree, key: &[u8]) -> Result<Option<IssuanceRecord>> {
+    match tree.get(key)? {
+        Some(v) => Ok(Some(serde_json::from_slice(&v)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}